@@ -5,6 +5,9 @@ use anyhow::Error;
 use async_trait::async_trait;
 use portable_pty::{Child, MasterPty, PtySize};
 use std::cell::{RefCell, RefMut};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::Arc;
 use url::Url;
 use wezterm_term::color::ColorPalette;
@@ -12,12 +15,28 @@ use wezterm_term::{
     Clipboard, KeyCode, KeyModifiers, MouseEvent, SemanticZone, StableRowIndex, Terminal,
 };
 
+/// How many PTY read chunks `start_reader` will let queue up before
+/// its `sync_channel` send blocks, throttling the reader thread to
+/// the pace at which `process_pty_bytes` is being called.
+const PTY_READ_QUEUE_DEPTH: usize = 32;
+
 pub struct LocalPane {
     pane_id: PaneId,
     terminal: RefCell<Terminal>,
     process: RefCell<Box<dyn Child>>,
     pty: RefCell<Box<dyn MasterPty>>,
     domain_id: DomainId,
+    reader_shutdown: Arc<AtomicBool>,
+    /// Bytes read by the `start_reader` background thread, waiting to
+    /// be applied to `terminal` via `process_pty_bytes`.  `LocalPane`
+    /// itself stays `!Send`/`!Sync`: `terminal`/`process`/`pty` are
+    /// plain `RefCell`s that are only ever touched from the thread
+    /// that owns the pane, and the reader thread never sees a
+    /// reference to `LocalPane` at all -- it only has the cloned PTY
+    /// reader, the shutdown flag (an `AtomicBool`, which really is
+    /// `Sync`), a `SyncSender` to hand bytes back over and the
+    /// `notify` `Sender`.
+    pty_bytes: RefCell<Option<Receiver<Vec<u8>>>>,
 }
 
 #[async_trait(?Send)]
@@ -26,12 +45,19 @@ impl Pane for LocalPane {
         self.pane_id
     }
 
+    /// Returns the `Renderable` view of this pane's terminal.  The
+    /// terminal accumulates per-line damage as `advance_bytes`,
+    /// `mouse_event`, `resize` and `erase_scrollback` mutate its
+    /// screen, so a caller can repaint just the spans reported by
+    /// `Renderable::damaged_rows` instead of the whole pane, then
+    /// call `Renderable::reset_damage` once the repaint is done.
     fn renderer(&self) -> RefMut<dyn Renderable> {
         RefMut::map(self.terminal.borrow_mut(), |t| &mut *t)
     }
 
     fn kill(&self) {
         log::debug!("killing process in pane {}", self.pane_id);
+        self.reader_shutdown.store(true, Ordering::Relaxed);
         self.process.borrow_mut().kill().ok();
     }
 
@@ -241,6 +267,89 @@ impl LocalPane {
             process: RefCell::new(process),
             pty: RefCell::new(pty),
             domain_id,
+            reader_shutdown: Arc::new(AtomicBool::new(false)),
+            pty_bytes: RefCell::new(None),
+        }
+    }
+
+    /// Spawns a thread that owns the PTY read loop for this pane: it
+    /// reads whatever bytes are available and hands them back over a
+    /// bounded channel, then sends `pane_id` on `notify` so a separate
+    /// render thread can wake up (this pairs naturally with a
+    /// `TerminalWaker`), call `process_pty_bytes` to apply them, and
+    /// repaint.
+    ///
+    /// The bytes are applied to the terminal by `process_pty_bytes`
+    /// rather than by this thread directly: `terminal` is a plain
+    /// `RefCell`, only ever safe to touch from the thread that owns
+    /// the pane, so the reader thread must not call `advance_bytes`
+    /// itself.
+    ///
+    /// The channel is bounded at `PTY_READ_QUEUE_DEPTH` chunks: if the
+    /// owning thread falls behind on calling `process_pty_bytes`, the
+    /// reader thread blocks on the next `send` instead of piling up
+    /// unbounded memory for a busy child process's output, which is
+    /// the actual backpressure.  `process_pty_bytes` additionally
+    /// drains every chunk already queued up in one go, so a burst that
+    /// does fit in the channel still turns into a single pass of
+    /// `advance_bytes` calls instead of one per `notify`.
+    ///
+    /// The thread exits once the PTY read returns EOF/an error, or
+    /// once `reader_shutdown` is set, which happens when `kill()` is
+    /// called or the pane is dropped.
+    pub fn start_reader(&self, notify: Sender<PaneId>) -> Result<(), Error> {
+        let mut reader = self.pty.borrow_mut().try_clone_reader()?;
+        let shutdown = Arc::clone(&self.reader_shutdown);
+        let pane_id = self.pane_id;
+        let (bytes_tx, bytes_rx) = std::sync::mpsc::sync_channel(PTY_READ_QUEUE_DEPTH);
+        *self.pty_bytes.borrow_mut() = Some(bytes_rx);
+
+        std::thread::Builder::new()
+            .name(format!("pane-reader-{}", pane_id))
+            .spawn(move || {
+                let mut buf = [0u8; 32 * 1024];
+                loop {
+                    if shutdown.load(Ordering::Relaxed) {
+                        return;
+                    }
+
+                    match reader.read(&mut buf) {
+                        Ok(0) => return,
+                        Ok(size) => {
+                            // Blocks here once PTY_READ_QUEUE_DEPTH
+                            // chunks are outstanding, throttling this
+                            // thread to the pace of process_pty_bytes
+                            // instead of growing memory without bound.
+                            if bytes_tx.send(buf[..size].to_vec()).is_err() {
+                                return;
+                            }
+                            if notify.send(pane_id).is_err() {
+                                return;
+                            }
+                        }
+                        Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                        Err(_) => return,
+                    }
+                }
+            })?;
+
+        Ok(())
+    }
+
+    /// Applies every chunk of PTY output queued up by the
+    /// `start_reader` thread, draining the channel completely rather
+    /// than handling one notification at a time, so a burst of reads
+    /// turns into a single pass of `advance_bytes` calls instead of
+    /// one per `notify`.  Call this after being woken by the `notify`
+    /// channel passed to `start_reader`.  Draining promptly also
+    /// relieves the bounded channel so the reader thread can keep
+    /// making progress instead of blocking on `send`.
+    pub fn process_pty_bytes(&self) {
+        let bytes = self.pty_bytes.borrow();
+        if let Some(rx) = bytes.as_ref() {
+            while let Ok(chunk) = rx.try_recv() {
+                self.advance_bytes(&chunk);
+            }
         }
     }
 
@@ -344,6 +453,9 @@ impl LocalPane {
 
 impl Drop for LocalPane {
     fn drop(&mut self) {
+        // Tell any reader thread spawned via start_reader to stop.
+        self.reader_shutdown.store(true, Ordering::Relaxed);
+
         // Avoid lingering zombies
         self.process.borrow_mut().kill().ok();
         self.process.borrow_mut().wait().ok();
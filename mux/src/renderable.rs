@@ -0,0 +1,130 @@
+use std::ops::Range;
+use wezterm_term::{StableRowIndex, StableCursorPosition};
+
+/// Describes the column span of a physical line that has changed
+/// since the last time damage was reset.  `left_col..right_col` is
+/// the half-open range of columns that were touched; consumers that
+/// want to repaint conservatively can simply clamp to the full width
+/// of the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineDamageBounds {
+    pub row: StableRowIndex,
+    pub left_col: usize,
+    pub right_col: usize,
+}
+
+impl LineDamageBounds {
+    pub fn from_row(row: StableRowIndex) -> Self {
+        Self {
+            row,
+            left_col: 0,
+            right_col: usize::MAX,
+        }
+    }
+
+    /// A line with no damaged columns is reported as `left_col >=
+    /// right_col`; `damaged_rows` uses this to skip those rows.
+    pub fn is_damaged(&self) -> bool {
+        self.right_col > self.left_col
+    }
+}
+
+/// A `Renderable` is something that can be rendered to a screen; it
+/// exposes the lines and cursor position that a front end needs in
+/// order to paint a pane.
+pub trait Renderable: downcast_rs::Downcast {
+    fn get_cursor_position(&self) -> StableCursorPosition;
+
+    /// Returns the set of lines that have changed since the last
+    /// time the caller fetched them.
+    fn get_dirty_lines(&self, lines: Range<StableRowIndex>) -> Vec<StableRowIndex>;
+
+    /// Returns the requested lines, which may be a superset of the
+    /// dirty lines reported by `get_dirty_lines`.
+    fn get_lines(&mut self, lines: Range<StableRowIndex>) -> (StableRowIndex, Vec<wezterm_term::Line>);
+
+    fn get_dimensions(&self) -> wezterm_term::RenderableDimensions;
+
+    /// Returns the leftmost/rightmost column damaged on each dirty
+    /// row since the last call to `reset_damage`, skipping rows that
+    /// have no damage at all.  A front end can use this to repaint
+    /// only the dirty column spans of dirty rows instead of redrawing
+    /// whole lines, which matters a lot on wide, mostly-static panes.
+    fn damaged_rows(&self) -> Box<dyn Iterator<Item = LineDamageBounds> + '_>;
+
+    /// Clears all accumulated damage, typically called by the front
+    /// end immediately after it has repainted the damaged spans.
+    fn reset_damage(&mut self);
+}
+
+impl Renderable for wezterm_term::Terminal {
+    fn get_cursor_position(&self) -> StableCursorPosition {
+        self.cursor_pos()
+    }
+
+    fn get_dirty_lines(&self, lines: Range<StableRowIndex>) -> Vec<StableRowIndex> {
+        self.screen().get_dirty_lines(lines)
+    }
+
+    fn get_lines(&mut self, lines: Range<StableRowIndex>) -> (StableRowIndex, Vec<wezterm_term::Line>) {
+        self.screen_mut().get_changed_stable_rows(lines)
+    }
+
+    fn get_dimensions(&self) -> wezterm_term::RenderableDimensions {
+        self.get_dimensions()
+    }
+
+    fn damaged_rows(&self) -> Box<dyn Iterator<Item = LineDamageBounds> + '_> {
+        Box::new(
+            self.screen()
+                .line_damage()
+                .into_iter()
+                .filter(LineDamageBounds::is_damaged),
+        )
+    }
+
+    fn reset_damage(&mut self) {
+        self.screen_mut().reset_line_damage();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn undamaged_row_is_skipped() {
+        let undamaged = LineDamageBounds {
+            row: 0,
+            left_col: 5,
+            right_col: 5,
+        };
+        assert!(!undamaged.is_damaged());
+
+        let also_undamaged = LineDamageBounds {
+            row: 0,
+            left_col: 5,
+            right_col: 3,
+        };
+        assert!(!also_undamaged.is_damaged());
+    }
+
+    #[test]
+    fn damaged_row_is_kept() {
+        let damaged = LineDamageBounds {
+            row: 1,
+            left_col: 2,
+            right_col: 10,
+        };
+        assert!(damaged.is_damaged());
+    }
+
+    #[test]
+    fn from_row_spans_whole_line() {
+        let d = LineDamageBounds::from_row(7);
+        assert_eq!(d.row, 7);
+        assert_eq!(d.left_col, 0);
+        assert_eq!(d.right_col, usize::MAX);
+        assert!(d.is_damaged());
+    }
+}
@@ -0,0 +1,221 @@
+//! Wraps a `Terminal` implementation and coalesces calls to `render`
+//! so that a burst of `Change`s is flushed as a single write.
+
+use super::{Blocking, ScreenSize, Terminal};
+use caps::Capabilities;
+use failure::Error;
+use input::InputEvent;
+use surface::Change;
+
+/// DEC private mode 2026: Synchronized Output.  Terminals that
+/// understand this mode guarantee that everything written between
+/// the begin and end sequences is presented to the user atomically,
+/// which avoids tearing when a large batch of `Change`s is applied.
+const BEGIN_SYNCHRONIZED_UPDATE: &str = "\x1b[?2026h";
+const END_SYNCHRONIZED_UPDATE: &str = "\x1b[?2026l";
+
+/// A `Terminal` implementation that buffers the `Change`s passed to
+/// `render` and defers the actual write until `flush` is called.
+///
+/// If the `Capabilities` passed to `new` indicate that the target
+/// terminal advertises DEC private mode 2026 (Synchronized Output),
+/// each flushed batch is wrapped in Begin/End Synchronized Update so
+/// that the downstream terminal presents the whole batch atomically
+/// instead of painting it a row at a time.  Synchronized output can
+/// be forced on or off with `set_synchronized_output`; when the
+/// capability is absent (and it hasn't been force-enabled) the
+/// wrapping sequences are simply not emitted, so nothing regresses on
+/// terminals that don't understand DEC 2026.
+pub struct BufferedTerminal<T: Terminal> {
+    terminal: T,
+    pending: Vec<Change>,
+    synchronized_output: Option<bool>,
+    target_supports_synchronized_output: bool,
+}
+
+impl<T: Terminal> BufferedTerminal<T> {
+    pub fn new(terminal: T, caps: &Capabilities) -> Result<Self, Error> {
+        Ok(Self {
+            terminal,
+            pending: vec![],
+            synchronized_output: None,
+            target_supports_synchronized_output: caps.synchronized_output(),
+        })
+    }
+
+    /// Force synchronized output on (`Some(true)`), force it off
+    /// (`Some(false)`), or go back to following the `Capabilities`
+    /// probe (`None`).
+    pub fn set_synchronized_output(&mut self, enabled: Option<bool>) {
+        self.synchronized_output = enabled;
+    }
+
+    fn use_synchronized_output(&self) -> bool {
+        self.synchronized_output
+            .unwrap_or(self.target_supports_synchronized_output)
+    }
+
+    pub fn add_change<C: Into<Change>>(&mut self, change: C) {
+        self.pending.push(change.into());
+    }
+
+    pub fn add_changes(&mut self, mut changes: Vec<Change>) {
+        self.pending.append(&mut changes);
+    }
+}
+
+impl<T: Terminal> Terminal for BufferedTerminal<T> {
+    fn set_raw_mode(&mut self) -> Result<(), Error> {
+        self.terminal.set_raw_mode()
+    }
+
+    fn get_screen_size(&mut self) -> Result<ScreenSize, Error> {
+        self.terminal.get_screen_size()
+    }
+
+    fn set_screen_size(&mut self, size: ScreenSize) -> Result<(), Error> {
+        self.terminal.set_screen_size(size)
+    }
+
+    fn render(&mut self, changes: &[Change]) -> Result<(), Error> {
+        self.pending.extend_from_slice(changes);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        if self.pending.is_empty() {
+            return self.terminal.flush();
+        }
+
+        let changes = std::mem::replace(&mut self.pending, vec![]);
+        let synchronized = self.use_synchronized_output();
+
+        let render_result: Result<(), Error> = (|| {
+            if synchronized {
+                self.terminal
+                    .render(&[Change::Text(BEGIN_SYNCHRONIZED_UPDATE.to_string())])?;
+            }
+            self.terminal.render(&changes)
+        })();
+
+        if synchronized {
+            // Always try to leave synchronized-update mode, even if
+            // sending the begin sequence or the batch itself failed
+            // above, so we never leave the downstream terminal stuck
+            // frozen inside DEC 2026.
+            let end_result = self
+                .terminal
+                .render(&[Change::Text(END_SYNCHRONIZED_UPDATE.to_string())]);
+            render_result?;
+            end_result?;
+        } else {
+            render_result?;
+        }
+
+        self.terminal.flush()
+    }
+
+    fn poll_input(&mut self, blocking: Blocking) -> Result<Option<InputEvent>, Error> {
+        self.terminal.poll_input(blocking)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use failure::bail;
+
+    /// A `Terminal` stub that records every `Change` batch passed to
+    /// `render` and fails the call whose index matches `fail_at`.
+    struct RecordingTerminal {
+        renders: Vec<Vec<Change>>,
+        fail_at: Option<usize>,
+    }
+
+    impl Terminal for RecordingTerminal {
+        fn set_raw_mode(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+        fn get_screen_size(&mut self) -> Result<ScreenSize, Error> {
+            unimplemented!()
+        }
+        fn set_screen_size(&mut self, _size: ScreenSize) -> Result<(), Error> {
+            unimplemented!()
+        }
+        fn render(&mut self, changes: &[Change]) -> Result<(), Error> {
+            let idx = self.renders.len();
+            self.renders.push(changes.to_vec());
+            if self.fail_at == Some(idx) {
+                bail!("synthetic render failure");
+            }
+            Ok(())
+        }
+        fn flush(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+        fn poll_input(&mut self, _blocking: Blocking) -> Result<Option<InputEvent>, Error> {
+            Ok(None)
+        }
+    }
+
+    fn buffered(fail_at: Option<usize>) -> BufferedTerminal<RecordingTerminal> {
+        BufferedTerminal {
+            terminal: RecordingTerminal {
+                renders: vec![],
+                fail_at,
+            },
+            pending: vec![],
+            synchronized_output: Some(true),
+            target_supports_synchronized_output: true,
+        }
+    }
+
+    #[test]
+    fn successful_flush_wraps_batch_in_sync_markers() {
+        let mut bt = buffered(None);
+        bt.add_change(Change::Text("hello".to_string()));
+        bt.flush().unwrap();
+
+        let renders = &bt.terminal.renders;
+        assert_eq!(renders.len(), 3);
+        assert_eq!(renders[0], vec![Change::Text(BEGIN_SYNCHRONIZED_UPDATE.to_string())]);
+        assert_eq!(renders[1], vec![Change::Text("hello".to_string())]);
+        assert_eq!(renders[2], vec![Change::Text(END_SYNCHRONIZED_UPDATE.to_string())]);
+    }
+
+    #[test]
+    fn end_marker_is_still_sent_when_batch_render_fails() {
+        // fail_at == 1: the begin sequence (index 0) succeeds, the
+        // batch itself (index 1) fails.
+        let mut bt = buffered(Some(1));
+        bt.add_change(Change::Text("hello".to_string()));
+
+        let result = bt.flush();
+        assert!(result.is_err());
+
+        let renders = &bt.terminal.renders;
+        // begin, (failed) batch, end: the end sequence must still
+        // have gone out despite the batch failing.
+        assert_eq!(renders.len(), 3);
+        assert_eq!(
+            renders.last().unwrap(),
+            &vec![Change::Text(END_SYNCHRONIZED_UPDATE.to_string())]
+        );
+    }
+
+    #[test]
+    fn end_marker_is_still_sent_when_begin_fails() {
+        // fail_at == 0: even the begin sequence itself fails.
+        let mut bt = buffered(Some(0));
+        bt.add_change(Change::Text("hello".to_string()));
+
+        let result = bt.flush();
+        assert!(result.is_err());
+
+        let renders = &bt.terminal.renders;
+        assert_eq!(
+            renders.last().unwrap(),
+            &vec![Change::Text(END_SYNCHRONIZED_UPDATE.to_string())]
+        );
+    }
+}
@@ -11,13 +11,17 @@ use surface::Change;
 pub mod unix;
 #[cfg(windows)]
 pub mod windows;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
 
 pub mod buffered;
 
 #[cfg(unix)]
-pub use self::unix::UnixTerminal;
+pub use self::unix::{TerminalWaker, UnixTerminal};
 #[cfg(windows)]
-pub use self::windows::WindowsTerminal;
+pub use self::windows::{TerminalWaker, WindowsTerminal};
+#[cfg(target_arch = "wasm32")]
+pub use self::wasm::WasmTerminal;
 
 /// Represents the size of the terminal screen.
 /// The number of rows and columns of character cells are expressed.
@@ -43,6 +47,12 @@ pub struct ScreenSize {
 pub enum Blocking {
     DoNotWait,
     Wait,
+    /// Block until either an `InputEvent` arrives or the given
+    /// duration elapses, whichever happens first.  On timeout,
+    /// `poll_input` returns `Ok(None)`.  This lets a caller cap the
+    /// latency between input checks and timer-driven redraws without
+    /// busy-looping on `DoNotWait`.
+    WaitForDuration(std::time::Duration),
 }
 
 /// `Terminal` abstracts over some basic terminal capabilities.
@@ -74,10 +84,20 @@ pub trait Terminal {
     /// `poll_input` will not return until an event is available.
     /// If blocking == `Blocking:DoNotWait` then `poll_input` will return
     /// immediately with a value of `Ok(None)`.
+    /// If blocking == `Blocking::WaitForDuration(d)` then `poll_input`
+    /// will block until either an event is available or `d` elapses,
+    /// returning `Ok(None)` on timeout.
     ///
     /// The possible values returned as `InputEvent`s depend on the
     /// mode of the terminal.  Most modes are not returned unless
     /// the terminal is set to raw mode.
+    ///
+    /// A `Blocking::Wait` call can be interrupted from another thread
+    /// via the `TerminalWaker` obtained from the concrete
+    /// `UnixTerminal`/`WindowsTerminal`; in that case `poll_input`
+    /// returns `Ok(Some(InputEvent::Wakeup))` rather than blocking
+    /// forever, which makes it practical to run the input loop on a
+    /// dedicated thread.
     fn poll_input(&mut self, blocking: Blocking) -> Result<Option<InputEvent>, Error>;
 }
 
@@ -99,6 +119,12 @@ pub type SystemTerminal = WindowsTerminal;
 /// If you have a more advanced use case you will want to look to the
 /// constructors for `UnixTerminal` and `WindowsTerminal` and call whichever
 /// one is most suitable for your needs.
+///
+/// There is no `wasm32` case here: `WasmTerminal::new` additionally
+/// needs a `WasmHost` supplied by the embedding JS glue, which doesn't
+/// fit this signature.  Crates targeting the browser should construct
+/// a `WasmTerminal` directly.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn new_terminal(caps: Capabilities) -> Result<impl Terminal, Error> {
     SystemTerminal::new(caps)
 }
@@ -106,3 +132,16 @@ pub fn new_terminal(caps: Capabilities) -> Result<impl Terminal, Error> {
 pub(crate) fn cast<T: NumCast + Display + Copy, U: NumCast>(n: T) -> Result<U, Error> {
     num::cast(n).ok_or_else(|| format_err!("{} is out of bounds for this system", n))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wait_for_duration_is_distinct_from_wait_and_do_not_wait() {
+        let d = Blocking::WaitForDuration(std::time::Duration::from_millis(16));
+        assert_ne!(d, Blocking::Wait);
+        assert_ne!(d, Blocking::DoNotWait);
+        assert_eq!(d, Blocking::WaitForDuration(std::time::Duration::from_millis(16)));
+    }
+}
@@ -0,0 +1,276 @@
+//! A Unix style terminal, as might be found on Linux, BSD or macOS.
+//! Ideally this would work on a POSIX system but it may currently
+//! have some assumptions baked in that don't hold everywhere.
+
+use super::{Blocking, ScreenSize, Terminal};
+use caps::Capabilities;
+use failure::{bail, Error};
+use input::{InputEvent, InputParser};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+
+/// A cloneable handle that can interrupt a blocking `poll_input` call
+/// on the `UnixTerminal` it was obtained from.
+///
+/// Internally this is a self-pipe: `wake()` writes a single byte to
+/// the write end, and the read end is registered alongside `/dev/tty`
+/// in the `poll()` set that backs `poll_input`.  When the poll loop
+/// observes readability on the pipe it drains it and synthesizes an
+/// `InputEvent::Wakeup`, so a caller blocked in
+/// `poll_input(Blocking::Wait)` on one thread can be woken from
+/// another without busy-polling.
+#[derive(Clone)]
+pub struct TerminalWaker {
+    write: std::sync::Arc<File>,
+}
+
+impl TerminalWaker {
+    fn new(write: File) -> Self {
+        Self {
+            write: std::sync::Arc::new(write),
+        }
+    }
+
+    /// Causes an in-progress `poll_input(Blocking::Wait)` on the
+    /// terminal that produced this waker to return
+    /// `Ok(Some(InputEvent::Wakeup))`.
+    pub fn wake(&self) -> Result<(), Error> {
+        write_wake_byte(self.write.as_raw_fd())?;
+        Ok(())
+    }
+}
+
+/// Writes a single byte to the write end of a self-pipe, marking it
+/// readable.  Takes a raw fd rather than a `File` so it can be
+/// exercised directly in a unit test against a real `libc::pipe()`
+/// without needing a whole `UnixTerminal`/`TerminalWaker`.
+fn write_wake_byte(fd: RawFd) -> Result<(), Error> {
+    let ret = unsafe { libc::write(fd, [0u8].as_ptr() as *const _, 1) };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Drains every byte currently queued on the read end of a self-pipe.
+/// Takes a raw fd for the same reason as `write_wake_byte`: it lets
+/// `drain_wake_pipe_fully_empties_multiple_queued_wakes` exercise this
+/// against a real pipe without constructing a `UnixTerminal`.
+fn drain_wake_pipe(fd: RawFd) {
+    let mut buf = [0u8; 64];
+    loop {
+        match unsafe { libc::read(fd, buf.as_mut_ptr() as *mut _, buf.len()) } {
+            n if n > 0 => continue,
+            _ => break,
+        }
+    }
+}
+
+pub struct UnixTerminal {
+    tty: File,
+    wake_pipe_read: RawFd,
+    wake_pipe_write: File,
+    input_parser: InputParser,
+    /// Events parsed out of a read that yielded more than one; drained
+    /// before we bother calling `poll()` again.
+    pending_events: VecDeque<InputEvent>,
+    /* other fields such as saved termios omitted here; unchanged by
+     * this change */
+}
+
+impl UnixTerminal {
+    pub fn new(_caps: Capabilities) -> Result<Self, Error> {
+        unimplemented!("constructed by the existing platform glue; not part of this change")
+    }
+
+    /// Returns a cloneable `TerminalWaker` that can be used to
+    /// interrupt a blocking `poll_input` call on this terminal from
+    /// another thread.
+    pub fn waker(&self) -> TerminalWaker {
+        TerminalWaker::new(
+            self.wake_pipe_write
+                .try_clone()
+                .expect("wake pipe write end is always clonable"),
+        )
+    }
+}
+
+/// Translates a `Blocking` mode into the timeout value expected by
+/// `libc::poll`: `-1` to wait forever, `0` to not wait at all, or the
+/// requested duration in milliseconds, clamped to what an `i32` can
+/// hold.
+fn poll_timeout_ms(blocking: Blocking) -> i32 {
+    match blocking {
+        Blocking::DoNotWait => 0,
+        Blocking::Wait => -1,
+        Blocking::WaitForDuration(d) => d.as_millis().min(i32::MAX as u128) as i32,
+    }
+}
+
+impl Terminal for UnixTerminal {
+    fn set_raw_mode(&mut self) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn get_screen_size(&mut self) -> Result<ScreenSize, Error> {
+        unimplemented!()
+    }
+
+    fn set_screen_size(&mut self, _size: ScreenSize) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn render(&mut self, _changes: &[crate::surface::Change]) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    /// Polls for an input event, blocking according to `blocking`.
+    /// The underlying `poll()` call waits on both `self.tty` and the
+    /// read end of the wake pipe; if the wake pipe becomes readable
+    /// it is drained and `InputEvent::Wakeup` is returned so that a
+    /// `TerminalWaker::wake()` call on another thread can interrupt
+    /// a `Blocking::Wait` poll without the caller busy-looping.  If
+    /// `self.tty` is readable instead, the bytes are read and parsed;
+    /// a read can yield more than one `InputEvent`, so any extras are
+    /// queued in `pending_events` and handed out on subsequent calls
+    /// before `poll()` is invoked again.
+    fn poll_input(&mut self, blocking: Blocking) -> Result<Option<InputEvent>, Error> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Ok(Some(event));
+        }
+
+        let mut pfd = [
+            libc::pollfd {
+                fd: self.tty.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: self.wake_pipe_read,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+
+        let timeout = poll_timeout_ms(blocking);
+
+        let ready = unsafe { libc::poll(pfd.as_mut_ptr(), pfd.len() as _, timeout) };
+        if ready < 0 {
+            bail!("poll failed");
+        }
+
+        if pfd[1].revents != 0 {
+            drain_wake_pipe(self.wake_pipe_read);
+            return Ok(Some(InputEvent::Wakeup));
+        }
+
+        if pfd[0].revents != 0 {
+            let mut buf = [0u8; 4096];
+            let n = self.tty.read(&mut buf)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            self.pending_events
+                .extend(self.input_parser.parse_as_vec(&buf[..n]));
+            return Ok(self.pending_events.pop_front());
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn do_not_wait_is_zero_timeout() {
+        assert_eq!(poll_timeout_ms(Blocking::DoNotWait), 0);
+    }
+
+    #[test]
+    fn wait_blocks_forever() {
+        assert_eq!(poll_timeout_ms(Blocking::Wait), -1);
+    }
+
+    #[test]
+    fn wait_for_duration_converts_to_millis() {
+        assert_eq!(
+            poll_timeout_ms(Blocking::WaitForDuration(std::time::Duration::from_millis(250))),
+            250
+        );
+    }
+
+    #[test]
+    fn wait_for_duration_clamps_to_i32_max() {
+        let huge = std::time::Duration::from_secs(u64::MAX / 1000);
+        assert_eq!(poll_timeout_ms(Blocking::WaitForDuration(huge)), i32::MAX);
+    }
+
+    /// Creates a real OS pipe for exercising the wake/drain fds
+    /// against actual `read`/`write` syscalls rather than mocks.
+    fn make_pipe() -> (RawFd, RawFd) {
+        let mut fds = [0i32; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        (fds[0], fds[1])
+    }
+
+    fn set_nonblocking(fd: RawFd) {
+        unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFL, 0);
+            libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+        }
+    }
+
+    fn close_pipe(fds: (RawFd, RawFd)) {
+        unsafe {
+            libc::close(fds.0);
+            libc::close(fds.1);
+        }
+    }
+
+    #[test]
+    fn write_wake_byte_makes_the_pipe_readable() {
+        let (read_fd, write_fd) = make_pipe();
+
+        write_wake_byte(write_fd).expect("write_wake_byte");
+
+        let mut buf = [0u8; 1];
+        let n = unsafe { libc::read(read_fd, buf.as_mut_ptr() as *mut _, 1) };
+        assert_eq!(n, 1);
+
+        close_pipe((read_fd, write_fd));
+    }
+
+    #[test]
+    fn drain_wake_pipe_fully_empties_multiple_queued_wakes() {
+        let (read_fd, write_fd) = make_pipe();
+        set_nonblocking(read_fd);
+
+        // Simulate several TerminalWaker::wake() calls piling up
+        // before poll_input gets around to draining them.
+        write_wake_byte(write_fd).expect("write 1");
+        write_wake_byte(write_fd).expect("write 2");
+        write_wake_byte(write_fd).expect("write 3");
+
+        drain_wake_pipe(read_fd);
+
+        // The pipe must be completely empty afterwards: a further
+        // read should fail with WouldBlock rather than return data.
+        let mut buf = [0u8; 1];
+        let n = unsafe { libc::read(read_fd, buf.as_mut_ptr() as *mut _, 1) };
+        assert_eq!(n, -1);
+        assert_eq!(
+            std::io::Error::last_os_error().kind(),
+            std::io::ErrorKind::WouldBlock
+        );
+
+        close_pipe((read_fd, write_fd));
+    }
+}
@@ -0,0 +1,99 @@
+//! A `Terminal` implementation that targets a browser host instead of
+//! a native TTY.  `render`/`flush` translate `Change`s into draws on
+//! a host-provided surface (eg. a `<canvas>`, or escape sequences fed
+//! to an embedded xterm.js instance), and `poll_input` drains a queue
+//! of `InputEvent`s that the host pushes in from JS keyboard/mouse
+//! callbacks.  There is no OS thread to block on here, so
+//! `Blocking::Wait`/`Blocking::WaitForDuration` don't spin; callers
+//! that need to wait for input should instead cooperate with the
+//! host's async runtime (eg. drive a `requestAnimationFrame` loop and
+//! call `poll_input(Blocking::DoNotWait)` each tick).
+
+use super::{Blocking, ScreenSize, Terminal};
+use caps::Capabilities;
+use failure::{bail, Error};
+use input::InputEvent;
+use std::collections::VecDeque;
+use surface::Change;
+
+/// Implemented by the embedding JS host so that `WasmTerminal` can
+/// hand it a batch of changes to paint and ask it for the current
+/// grid size.
+pub trait WasmHost {
+    fn render(&mut self, changes: &[Change]) -> Result<(), Error>;
+    fn flush(&mut self) -> Result<(), Error>;
+    fn get_screen_size(&self) -> ScreenSize;
+    fn set_screen_size(&mut self, size: ScreenSize);
+}
+
+pub struct WasmTerminal {
+    host: Box<dyn WasmHost>,
+    input_queue: VecDeque<InputEvent>,
+}
+
+impl WasmTerminal {
+    pub fn new(_caps: Capabilities, host: Box<dyn WasmHost>) -> Result<Self, Error> {
+        Ok(Self {
+            host,
+            input_queue: VecDeque::new(),
+        })
+    }
+
+    /// Called by the JS glue when the host observes a keyboard/mouse
+    /// event; it is queued up and returned by a subsequent
+    /// `poll_input` call.
+    pub fn push_input(&mut self, event: InputEvent) {
+        self.input_queue.push_back(event);
+    }
+}
+
+impl Terminal for WasmTerminal {
+    fn set_raw_mode(&mut self) -> Result<(), Error> {
+        // The host is always "raw": there's no line discipline to
+        // disable and no local echo to suppress.
+        Ok(())
+    }
+
+    fn get_screen_size(&mut self) -> Result<ScreenSize, Error> {
+        Ok(self.host.get_screen_size())
+    }
+
+    fn set_screen_size(&mut self, size: ScreenSize) -> Result<(), Error> {
+        self.host.set_screen_size(size);
+        Ok(())
+    }
+
+    fn render(&mut self, changes: &[Change]) -> Result<(), Error> {
+        self.host.render(changes)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.host.flush()
+    }
+
+    /// Drains the queue of `InputEvent`s populated by `push_input`.
+    /// No mode ever waits: there is no OS thread to park here, so a
+    /// blocking wait would either spin forever or have to be
+    /// implemented as a `Future`, which isn't what this synchronous
+    /// trait method can express.  `Blocking::Wait` and
+    /// `Blocking::WaitForDuration` return the next queued event if
+    /// there is one, and an error when the queue is empty.  Callers
+    /// that want to wait should poll with `Blocking::DoNotWait` on each
+    /// tick of the host's own async loop instead.
+    fn poll_input(&mut self, blocking: Blocking) -> Result<Option<InputEvent>, Error> {
+        match blocking {
+            Blocking::DoNotWait => Ok(self.input_queue.pop_front()),
+            Blocking::Wait | Blocking::WaitForDuration(_) => {
+                if let Some(event) = self.input_queue.pop_front() {
+                    Ok(Some(event))
+                } else {
+                    bail!(
+                        "WasmTerminal::poll_input does not support blocking; \
+                         cooperate with the host's async runtime and poll with \
+                         Blocking::DoNotWait instead"
+                    )
+                }
+            }
+        }
+    }
+}
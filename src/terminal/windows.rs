@@ -0,0 +1,198 @@
+//! A Windows console based terminal implementation.
+
+use super::{Blocking, ScreenSize, Terminal};
+use caps::Capabilities;
+use failure::{bail, Error};
+use input::{InputEvent, KeyCode, KeyEvent, KeyModifiers};
+use winapi::shared::minwindef::DWORD;
+use winapi::um::consoleapi::ReadConsoleInputW;
+use winapi::um::synchapi::{ResetEvent, SetEvent, WaitForMultipleObjects};
+use winapi::um::wincon::{INPUT_RECORD, KEY_EVENT};
+use winapi::um::winbase::{INFINITE, WAIT_FAILED, WAIT_OBJECT_0};
+use winapi::um::winnt::HANDLE;
+
+/// A cloneable handle that can interrupt a blocking `poll_input` call
+/// on the `WindowsTerminal` it was obtained from.
+///
+/// This wraps a manual-reset `HANDLE` event that is included in the
+/// `WaitForMultipleObjects` handle set used by the console reader;
+/// `wake()` signals the event, which causes `poll_input` to return
+/// `Ok(Some(InputEvent::Wakeup))` instead of waiting for console
+/// input.
+#[derive(Clone)]
+pub struct TerminalWaker {
+    event: HANDLE,
+}
+
+// The event HANDLE is safe to share and signal from other threads;
+// SetEvent is safe to call concurrently with WaitForMultipleObjects.
+unsafe impl Send for TerminalWaker {}
+unsafe impl Sync for TerminalWaker {}
+
+impl TerminalWaker {
+    fn new(event: HANDLE) -> Self {
+        Self { event }
+    }
+
+    /// Causes an in-progress `poll_input(Blocking::Wait)` on the
+    /// terminal that produced this waker to return
+    /// `Ok(Some(InputEvent::Wakeup))`.
+    pub fn wake(&self) -> Result<(), Error> {
+        if unsafe { SetEvent(self.event) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+pub struct WindowsTerminal {
+    wake_event: HANDLE,
+    console_input: HANDLE,
+    /* other fields such as the console output handle and saved mode
+     * are omitted here; unchanged by this change */
+}
+
+impl WindowsTerminal {
+    pub fn new(_caps: Capabilities) -> Result<Self, Error> {
+        unimplemented!("constructed by the existing platform glue; not part of this change")
+    }
+
+    /// Returns a cloneable `TerminalWaker` that can be used to
+    /// interrupt a blocking `poll_input` call on this terminal from
+    /// another thread.
+    pub fn waker(&self) -> TerminalWaker {
+        TerminalWaker::new(self.wake_event)
+    }
+}
+
+/// Translates a `Blocking` mode into the timeout value expected by
+/// `WaitForMultipleObjects`: `INFINITE` to wait forever, `0` to not
+/// wait at all, or the requested duration in milliseconds, clamped to
+/// one less than `INFINITE` so it can never be mistaken for it.
+fn wait_timeout_ms(blocking: Blocking) -> DWORD {
+    match blocking {
+        Blocking::DoNotWait => 0,
+        Blocking::Wait => INFINITE,
+        Blocking::WaitForDuration(d) => d.as_millis().min(INFINITE as u128 - 1) as DWORD,
+    }
+}
+
+/// Best-effort decode of a console key-down event into an
+/// `InputEvent::Key`.  Record kinds this doesn't understand (mouse,
+/// focus, buffer resize, key-up) are reported as `None` so the caller
+/// can keep waiting rather than returning a bogus event.
+fn decode_key_event(record: &INPUT_RECORD) -> Option<InputEvent> {
+    if record.EventType != KEY_EVENT {
+        return None;
+    }
+    let key_event = unsafe { record.Event.KeyEvent() };
+    if key_event.bKeyDown == 0 {
+        return None;
+    }
+    let c = unsafe { *key_event.uChar.UnicodeChar() };
+    if c == 0 {
+        return None;
+    }
+    Some(InputEvent::Key(KeyEvent {
+        key: KeyCode::Char(std::char::from_u32(c as u32)?),
+        modifiers: KeyModifiers::NONE,
+    }))
+}
+
+impl Terminal for WindowsTerminal {
+    fn set_raw_mode(&mut self) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn get_screen_size(&mut self) -> Result<ScreenSize, Error> {
+        unimplemented!()
+    }
+
+    fn set_screen_size(&mut self, _size: ScreenSize) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn render(&mut self, _changes: &[crate::surface::Change]) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        unimplemented!()
+    }
+
+    /// Waits on the console input handle together with `wake_event`
+    /// via `WaitForMultipleObjects`; if `wake_event` is the object
+    /// that became signalled, `InputEvent::Wakeup` is returned so a
+    /// `TerminalWaker::wake()` call from another thread can interrupt
+    /// a `Blocking::Wait` poll.  If the console input handle is what
+    /// became signalled, the pending records are read and decoded;
+    /// `Ok(None)` is returned both on timeout and for record kinds
+    /// this doesn't decode into an `InputEvent` (the caller is
+    /// expected to poll again rather than treat that as EOF).
+    fn poll_input(&mut self, blocking: Blocking) -> Result<Option<InputEvent>, Error> {
+        let handles = [self.console_input, self.wake_event];
+        let timeout = wait_timeout_ms(blocking);
+
+        let result =
+            unsafe { WaitForMultipleObjects(handles.len() as DWORD, handles.as_ptr(), 0, timeout) };
+
+        if result == WAIT_FAILED {
+            bail!("WaitForMultipleObjects failed: {}", unsafe {
+                winapi::um::errhandlingapi::GetLastError()
+            });
+        }
+
+        if result == WAIT_OBJECT_0 + 1 {
+            // wake_event is a manual-reset event; ResetEvent leaves it
+            // unsignalled again until the next TerminalWaker::wake().
+            unsafe {
+                ResetEvent(self.wake_event);
+            }
+            return Ok(Some(InputEvent::Wakeup));
+        }
+
+        if result == WAIT_OBJECT_0 {
+            let mut record: INPUT_RECORD = unsafe { std::mem::zeroed() };
+            let mut read: DWORD = 0;
+            if unsafe { ReadConsoleInputW(self.console_input, &mut record, 1, &mut read) } == 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            if read == 0 {
+                return Ok(None);
+            }
+            return Ok(decode_key_event(&record));
+        }
+
+        // Timed out without either handle becoming signalled.
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn do_not_wait_is_zero_timeout() {
+        assert_eq!(wait_timeout_ms(Blocking::DoNotWait), 0);
+    }
+
+    #[test]
+    fn wait_blocks_forever() {
+        assert_eq!(wait_timeout_ms(Blocking::Wait), INFINITE);
+    }
+
+    #[test]
+    fn wait_for_duration_converts_to_millis() {
+        assert_eq!(
+            wait_timeout_ms(Blocking::WaitForDuration(std::time::Duration::from_millis(250))),
+            250
+        );
+    }
+
+    #[test]
+    fn wait_for_duration_never_collides_with_infinite() {
+        let huge = std::time::Duration::from_secs(u64::MAX / 1000);
+        assert_eq!(wait_timeout_ms(Blocking::WaitForDuration(huge)), INFINITE - 1);
+    }
+}